@@ -2,19 +2,20 @@ use futures::StreamExt;
 use itertools::Itertools;
 use semver::Version;
 use serde::de::DeserializeOwned;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::process::Stdio;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use stream_cancel::{StreamExt as ScStreamExt, Trigger, Tripwire};
 use structopt::StructOpt;
 use tempdir::TempDir;
 use tokio::process;
 use tokio::select;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Semaphore};
 use tracing::{debug, error, info, Level};
 use tracing_subscriber::EnvFilter;
 
@@ -23,6 +24,24 @@ struct Opts {
     cfg: PathBuf,
     #[structopt(long)]
     install: bool,
+    /// Write a JUnit XML report to this path, overriding `report.junit_path` from the config
+    #[structopt(long)]
+    junit_report: Option<PathBuf>,
+    /// Write a JSON summary report to this path, overriding `report.summary_path` from the config
+    #[structopt(long)]
+    json_report: Option<PathBuf>,
+    /// Instead of running the full matrix, bisect `cfg.rust` for each feature-set to find the
+    /// lowest toolchain it builds on and print a table of discovered `min_rust` values
+    #[structopt(long)]
+    find_msrv: bool,
+    /// Instead of running the full matrix, verify the configured toolchains against a
+    /// `-Z minimal-versions` lockfile and print any `VersionPin`s needed to make it pass
+    #[structopt(long)]
+    minimal_versions: bool,
+    /// On a failing unit, apply rustc's machine-applicable suggestions in its isolated workdir
+    /// and re-run the test to see if that alone fixes it
+    #[structopt(long)]
+    fix: bool,
 }
 
 #[derive(Clone, Deserialize)]
@@ -32,6 +51,13 @@ struct Config {
     rust: Vec<RustVersion>,
     par: usize,
     fuzzing: Option<Fuzzing>,
+    report: Option<ReportConfig>,
+}
+
+#[derive(Clone, Deserialize)]
+struct ReportConfig {
+    junit_path: Option<PathBuf>,
+    summary_path: Option<PathBuf>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -47,7 +73,7 @@ struct RustVersion {
     requires_pinning: Vec<VersionPin>,
 }
 
-#[derive(Clone, Deserialize, Eq, PartialEq, Hash)]
+#[derive(Clone, Deserialize, Serialize, Eq, PartialEq, Hash)]
 struct VersionPin {
     dependency: String,
     version: Version,
@@ -58,6 +84,12 @@ struct Fuzzing {
     rel_path: PathBuf,
     rust: String,
     duration_s: u64,
+    /// Path (relative to `rel_path`) of a directory holding `<target>/input` seed corpora
+    /// to warm-start each run with, mirroring the per-target layout `cargo hfuzz` itself uses.
+    corpus_rel_path: Option<PathBuf>,
+    /// Durable directory crashing inputs are copied out to, each run's crashes in its own
+    /// `<target>/<timestamp>` subdirectory.
+    crashes_out: Option<PathBuf>,
 }
 
 pub fn load_from_file<T: DeserializeOwned>(path: &Path) -> T {
@@ -124,36 +156,61 @@ fn versions_geq(v1: &str, v2: &str, stable: &Version) -> bool {
     }
 }
 
+fn feature_sets_for_rust(cfg: &Config, rust: &RustVersion, stable_version: &Version) -> Vec<Vec<Feature>> {
+    cfg.features
+        .iter()
+        .filter(|f| {
+            if let Some(min_rust_version) = f.min_rust.as_ref() {
+                versions_geq(&rust.name, min_rust_version, stable_version)
+            } else {
+                true
+            }
+        })
+        .cloned()
+        .powerset()
+        .collect()
+}
+
 async fn gen_test_matrix(cfg: &Config) -> HashMap<RustVersion, Vec<Vec<Feature>>> {
     let stable_version = get_stable_version().await;
     cfg.rust
         .iter()
         .cloned()
         .map(|rust| {
-            let feature_sets = cfg
-                .features
-                .iter()
-                .filter(|f| {
-                    if let Some(min_rust_version) = f.min_rust.as_ref() {
-                        versions_geq(&rust.name, min_rust_version, &stable_version)
-                    } else {
-                        true
-                    }
-                })
-                .cloned()
-                .powerset()
-                .collect::<Vec<_>>();
+            let feature_sets = feature_sets_for_rust(cfg, &rust, &stable_version);
             (rust, feature_sets)
         })
         .collect::<HashMap<_, _>>()
 }
 
-async fn test_rust_version(
-    cfg: Config,
-    rust: RustVersion,
-    feature_sets: Vec<Vec<Feature>>,
-    delete_path_sender: mpsc::Sender<PathBuf>,
-) {
+/// A toolchain's copy of the repo, kept alive for as long as any job still needs it.
+///
+/// Wrapped in an `Arc` and handed out to every feature-set job scheduled against this
+/// `RustVersion`; once the last clone is dropped the `TempDir` is removed, so cleanup
+/// happens exactly when the last job for this toolchain finishes rather than being tied
+/// to a single sequential run.
+struct PreparedEnv {
+    _tmp_dir: TempDir,
+    workdir: PathBuf,
+}
+
+async fn prepare_rust_version(
+    cfg: &Config,
+    rust: &RustVersion,
+    delete_path_sender: &mpsc::Sender<PathBuf>,
+) -> Arc<PreparedEnv> {
+    prepare_rust_version_with_lockfile(cfg, rust, None, delete_path_sender).await
+}
+
+/// Like [`prepare_rust_version`], but if `lockfile` is given its contents are copied in as
+/// `Cargo.lock` instead of deleting whatever lockfile the repo ships with — used to pin every
+/// toolchain to a shared, pre-resolved lockfile (e.g. a `-Z minimal-versions` one).
+async fn prepare_rust_version_with_lockfile(
+    cfg: &Config,
+    rust: &RustVersion,
+    lockfile: Option<&Path>,
+    delete_path_sender: &mpsc::Sender<PathBuf>,
+) -> Arc<PreparedEnv> {
     info!("Preparing environment for rust {} tests", rust.name);
     let project_name = cfg.repo.iter().last().unwrap().to_str().unwrap();
     let tmp_dir = TempDir::new(&format!("{}-{}", project_name, rust.name)).unwrap();
@@ -183,30 +240,173 @@ async fn test_rust_version(
         workdir.as_os_str().to_string_lossy()
     );
 
+    if let Some(lockfile) = lockfile {
+        std::fs::copy(lockfile, workdir.join("Cargo.lock")).unwrap();
+    }
+
     if !rust.requires_pinning.is_empty() {
-        pin_dependencies(&workdir, &rust).await;
+        pin_dependencies(&workdir, rust).await;
     }
 
-    for feature_set in feature_sets {
-        run_test(&workdir, &rust, &feature_set).await;
+    Arc::new(PreparedEnv {
+        _tmp_dir: tmp_dir,
+        workdir,
+    })
+}
+
+/// Every feature-set job for a toolchain is handed the same `PreparedEnv` (and so the same
+/// `workdir`) to keep the checkout-and-pin work in [`prepare_rust_version`] from happening once
+/// per job; `CARGO_TARGET_DIR` already gives each job its own build output, but `--fix` mutates
+/// *source* files, which would otherwise race every sibling job still compiling or reading that
+/// same shared workdir. Give the unit being fixed its own private source copy instead, so
+/// applying suggestions there really is side-effect-free.
+async fn clone_workdir_for_fix(
+    source: &Path,
+    delete_path_sender: &mpsc::Sender<PathBuf>,
+) -> PreparedEnv {
+    let project_name = source.file_name().unwrap().to_os_string();
+    let tmp_dir = TempDir::new("fix").unwrap();
+
+    delete_path_sender
+        .send(tmp_dir.path().to_path_buf())
+        .await
+        .unwrap();
+
+    let tmp_dir_path = tmp_dir.path().to_path_buf();
+    let source = source.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut copy_options = fs_extra::dir::CopyOptions::new();
+        copy_options.copy_inside = true;
+        fs_extra::dir::copy(source, &tmp_dir_path, &copy_options).unwrap();
+    })
+    .await
+    .unwrap();
+
+    let workdir = tmp_dir.path().join(project_name);
+    PreparedEnv {
+        _tmp_dir: tmp_dir,
+        workdir,
     }
 }
 
-async fn run_test(path: &Path, rust: &RustVersion, feature_set: &[Feature]) {
+/// Outcome of a single `#[test]` as reported by libtest's JSON output, tagged with the
+/// `(rust, feature_set)` unit it ran under so the aggregated report can pin a failure down
+/// to the exact toolchain/feature combination that broke it.
+#[derive(Clone, Debug, Serialize)]
+struct TestOutcome {
+    rust: String,
+    feature_set: String,
+    name: String,
+    passed: bool,
+    duration_s: Option<f64>,
+}
+
+/// Fallback for toolchains where libtest's JSON output isn't available: parses the default
+/// plain-text harness output (`test <name> ... ok`/`... FAILED`), which carries no duration.
+fn parse_test_events_plain(stdout: &[u8], rust: &str, feature_str: &str) -> Vec<TestOutcome> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("test ")?;
+            let (name, outcome) = rest.rsplit_once(" ... ")?;
+            let passed = match outcome.split_whitespace().next()? {
+                "ok" => true,
+                "FAILED" => false,
+                _ => return None,
+            };
+            Some(TestOutcome {
+                rust: rust.to_string(),
+                feature_set: feature_str.to_string(),
+                name: name.to_string(),
+                passed,
+                duration_s: None,
+            })
+        })
+        .collect()
+}
+
+/// Parses the newline-delimited JSON emitted by `cargo test --message-format=json` combined
+/// with `-- --format=json`, picking out the individual libtest `"type": "test"` events and
+/// ignoring cargo's own compiler-artifact/compiler-message lines.
+fn parse_test_events(stdout: &[u8], rust: &str, feature_str: &str) -> Vec<TestOutcome> {
+    stdout
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| serde_json::from_slice::<serde_json::Value>(line).ok())
+        .filter(|msg| msg.get("type").and_then(|t| t.as_str()) == Some("test"))
+        .filter_map(|msg| {
+            let event = msg.get("event").and_then(|e| e.as_str())?;
+            if event != "ok" && event != "failed" {
+                return None;
+            }
+            Some(TestOutcome {
+                rust: rust.to_string(),
+                feature_set: feature_str.to_string(),
+                name: msg.get("name")?.as_str()?.to_string(),
+                passed: event == "ok",
+                duration_s: msg.get("exec_time").and_then(|d| d.as_f64()),
+            })
+        })
+        .collect()
+}
+
+/// `-- --format=json -Z unstable-options` makes libtest itself emit structured per-test
+/// events, but `-Z` flags are rejected outright on anything but the nightly compiler, so we
+/// only ask for it there; other toolchains fall back to parsing libtest's plain text output.
+fn supports_libtest_json(rust: &RustVersion) -> bool {
+    rust.name == "nightly"
+}
+
+async fn exec_cargo_test(
+    path: &Path,
+    rust: &RustVersion,
+    feature_set: &[Feature],
+    target_dir: Option<&Path>,
+) -> std::process::Output {
     let feature_str = feature_set.iter().map(|f| &f.name).join(",");
-    let cargo = process::Command::new("cargo")
+    let mut cargo = process::Command::new("cargo");
+    cargo
         .current_dir(path)
         .arg(format!("+{}", rust.name))
         .arg("test")
         .arg("--no-default-features")
         .arg("--features")
         .arg(&feature_str)
+        .arg("--message-format=json");
+
+    if let Some(target_dir) = target_dir {
+        // Every feature-set job for a toolchain shares the same checked-out workdir, so
+        // without a distinct CARGO_TARGET_DIR per job concurrent `cargo test` invocations
+        // would serialize on cargo's build-directory lock instead of actually running in
+        // parallel.
+        cargo.env("CARGO_TARGET_DIR", target_dir);
+    }
+
+    if supports_libtest_json(rust) {
+        cargo
+            .arg("--")
+            .arg("--format=json")
+            .arg("-Z")
+            .arg("unstable-options");
+    }
+
+    let cargo = cargo
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
         .expect("cargo failed to execute");
 
-    let output = cargo.wait_with_output().await.unwrap();
+    cargo.wait_with_output().await.unwrap()
+}
+
+async fn run_test(
+    path: &Path,
+    rust: &RustVersion,
+    feature_set: &[Feature],
+    target_dir: Option<&Path>,
+) -> (std::process::Output, Vec<TestOutcome>) {
+    let feature_str = feature_set.iter().map(|f| &f.name).join(",");
+    let output = exec_cargo_test(path, rust, feature_set, target_dir).await;
     if output.status.success() {
         info!(
             "Test rust={}, features=[{}] succeeded!",
@@ -222,6 +422,548 @@ async fn run_test(path: &Path, rust: &RustVersion, feature_set: &[Feature]) {
         info!("std err:\n");
         std::io::stdout().write_all(&output.stderr).unwrap();
     }
+
+    let mut outcomes = if supports_libtest_json(rust) {
+        parse_test_events(&output.stdout, &rust.name, &feature_str)
+    } else {
+        parse_test_events_plain(&output.stdout, &rust.name, &feature_str)
+    };
+
+    if !output.status.success() && outcomes.is_empty() {
+        // A unit that fails to even compile emits no per-test events at all; without a
+        // synthetic record the whole (rust, feature_set) combination would silently vanish
+        // from the report instead of showing up as the thing that broke.
+        outcomes.push(TestOutcome {
+            rust: rust.name.clone(),
+            feature_set: feature_str.clone(),
+            name: "<build>".to_string(),
+            passed: false,
+            duration_s: None,
+        });
+    }
+
+    (output, outcomes)
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_junit_report(results: &[TestOutcome], path: &Path) {
+    let mut suites: HashMap<(&str, &str), Vec<&TestOutcome>> = HashMap::new();
+    for result in results {
+        suites
+            .entry((&result.rust, &result.feature_set))
+            .or_default()
+            .push(result);
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+    for ((rust, feature_set), cases) in suites {
+        let failures = cases.iter().filter(|c| !c.passed).count();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}:{}\" tests=\"{}\" failures=\"{}\">\n",
+            escape_xml(rust),
+            escape_xml(feature_set),
+            cases.len(),
+            failures
+        ));
+        for case in cases {
+            let time = case.duration_s.unwrap_or(0.0);
+            let name = escape_xml(&case.name);
+            if case.passed {
+                xml.push_str(&format!(
+                    "    <testcase name=\"{}\" time=\"{}\" />\n",
+                    name, time
+                ));
+            } else {
+                xml.push_str(&format!(
+                    "    <testcase name=\"{}\" time=\"{}\"><failure /></testcase>\n",
+                    name, time
+                ));
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+
+    std::fs::write(path, xml).expect("Could not write JUnit report");
+}
+
+#[derive(Serialize)]
+struct MsrvResult {
+    feature_set: String,
+    min_rust: Option<String>,
+}
+
+/// Ascending order (lowest toolchain first), using the same `versions_geq` notion of
+/// "newer" that `gen_test_matrix` uses to decide whether a feature's `min_rust` is satisfied.
+fn sort_rust_versions(mut versions: Vec<RustVersion>, stable: &Version) -> Vec<RustVersion> {
+    versions.sort_by(|a, b| {
+        let a_geq_b = versions_geq(&a.name, &b.name, stable);
+        let b_geq_a = versions_geq(&b.name, &a.name, stable);
+        if a_geq_b && b_geq_a {
+            // e.g. "stable" and its literal numeric equivalent both resolve to the same
+            // semver; neither is greater, so treat them as equal rather than letting
+            // whichever arm runs first win.
+            std::cmp::Ordering::Equal
+        } else if a_geq_b {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Less
+        }
+    });
+    versions
+}
+
+/// Runs `(rust, feature_set)` through `run_test`, reusing a prepared toolchain workdir and a
+/// pass/fail cache across calls so that feature sets sharing a powerset prefix, or a binary
+/// search revisiting the same toolchain, don't re-run `cargo test` for a unit already known.
+///
+/// The cache is keyed on the feature set as an order-independent `BTreeSet` (not the
+/// comma-joined string) so that any two powerset elements naming the same features collide.
+/// It's also consulted transitively: enabling features only ever adds code to build and run,
+/// so a superset of a feature set already known to fail on `rust` is known to fail too, and a
+/// subset of a feature set already known to pass is known to pass too.
+async fn test_unit_cached(
+    env_cache: &mut HashMap<String, Arc<PreparedEnv>>,
+    result_cache: &mut HashMap<(String, BTreeSet<String>), bool>,
+    cfg: &Config,
+    rust: &RustVersion,
+    feature_set: &[Feature],
+    delete_path_sender: &mpsc::Sender<PathBuf>,
+) -> bool {
+    let features: BTreeSet<String> = feature_set.iter().map(|f| f.name.clone()).collect();
+    let cache_key = (rust.name.clone(), features.clone());
+    if let Some(&passed) = result_cache.get(&cache_key) {
+        return passed;
+    }
+    for ((other_rust, other_features), &passed) in result_cache.iter() {
+        if other_rust != &rust.name {
+            continue;
+        }
+        if !passed && other_features.is_subset(&features) {
+            result_cache.insert(cache_key, false);
+            return false;
+        }
+        if passed && other_features.is_superset(&features) {
+            result_cache.insert(cache_key, true);
+            return true;
+        }
+    }
+
+    let env = match env_cache.get(&rust.name) {
+        Some(env) => env.clone(),
+        None => {
+            let env = prepare_rust_version(cfg, rust, delete_path_sender).await;
+            env_cache.insert(rust.name.clone(), env.clone());
+            env
+        }
+    };
+
+    let (output, _) = run_test(&env.workdir, rust, feature_set, None).await;
+    let passed = output.status.success();
+    result_cache.insert(cache_key, passed);
+    passed
+}
+
+/// For every element of the feature powerset, binary-searches the ascending toolchain list
+/// for the lowest `RustVersion` on which `cargo test` passes. Relies on "builds on version N"
+/// being monotonic in N (if it builds on an older toolchain it builds on newer ones too), so
+/// each feature set takes `log2(len(cfg.rust))` units instead of a linear scan.
+async fn find_msrv(cfg: &Config, delete_path_sender: &mpsc::Sender<PathBuf>) -> Vec<MsrvResult> {
+    let stable_version = get_stable_version().await;
+    let sorted_rust = sort_rust_versions(cfg.rust.clone(), &stable_version);
+
+    let mut env_cache = HashMap::new();
+    let mut result_cache = HashMap::new();
+    let mut results = Vec::new();
+
+    for feature_set in cfg.features.iter().cloned().powerset() {
+        let feature_str = feature_set.iter().map(|f| &f.name).join(",");
+
+        let mut lo = 0usize;
+        let mut hi = sorted_rust.len();
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let passed = test_unit_cached(
+                &mut env_cache,
+                &mut result_cache,
+                cfg,
+                &sorted_rust[mid],
+                &feature_set,
+                delete_path_sender,
+            )
+            .await;
+            if passed {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        let min_rust = sorted_rust.get(lo).map(|r| r.name.clone());
+        info!(
+            "features=[{}]: min_rust={}",
+            feature_str,
+            min_rust.as_deref().unwrap_or("none found")
+        );
+        results.push(MsrvResult {
+            feature_set: feature_str,
+            min_rust,
+        });
+    }
+
+    results
+}
+
+/// Forces every dependency to the oldest version its semver requirement allows, the same way
+/// cargo's own `-Z minimal-versions` resolver work catches under-specified requirements: a
+/// crate declaring `foo = "1"` but actually needing an API added in `1.2` builds fine against
+/// whatever recent `foo` happens to be in a normal lockfile, but fails the moment `foo 1.0` is
+/// actually resolved. Only available on nightly.
+async fn generate_minimal_lockfile(workdir: &Path) {
+    let mut cargo = process::Command::new("cargo")
+        .current_dir(workdir)
+        .arg("+nightly")
+        .arg("generate-lockfile")
+        .arg("-Z")
+        .arg("minimal-versions")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("cargo failed to execute");
+    assert!(cargo.wait().await.unwrap().success());
+}
+
+/// Every crate name recorded in `workdir`'s `Cargo.lock`, in the same ad-hoc `[[package]]`-block
+/// parsing `locked_version` uses (no lockfile-format crate pulled in for this one lookup).
+fn locked_crate_names(workdir: &Path) -> Vec<String> {
+    let lockfile = match std::fs::read_to_string(workdir.join("Cargo.lock")) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+    lockfile
+        .split("[[package]]")
+        .filter_map(|block| {
+            block
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("name = \""))
+                .and_then(|rest| rest.strip_suffix('"'))
+                .map(|name| name.to_string())
+        })
+        .collect()
+}
+
+/// Pulls the crate name responsible for a minimal-versions failure out of `output`. Tries the
+/// resolver's own error for an under-constrained requirement first, e.g.
+/// `error: failed to select a version for `foo`.`. That only fires when resolution itself
+/// fails, which is rare - the far more common case described above is that resolution succeeds
+/// but the locked (minimal) version of some dependency is missing an API the crate actually
+/// uses, which surfaces as an ordinary compiler error in the local crate, not a resolver error.
+/// For that case, fall back to scanning the compiler diagnostics for a `<crate>::` path naming
+/// one of the lockfile's other dependencies.
+fn find_offending_crate(workdir: &Path, output: &std::process::Output) -> Option<String> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let prefix = "error: failed to select a version for `";
+    if let Some(name) = stderr.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.split('`').next())
+            .map(|name| name.to_string())
+    }) {
+        return Some(name);
+    }
+
+    let project_name = workdir.file_name().and_then(|name| name.to_str());
+    let known_crates: Vec<String> = locked_crate_names(workdir)
+        .into_iter()
+        .filter(|name| Some(name.as_str()) != project_name)
+        .collect();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|msg| msg.reason == "compiler-message")
+        .filter_map(|msg| msg.message)
+        .filter_map(|message| message.rendered)
+        .find_map(|rendered| {
+            known_crates
+                .iter()
+                .find(|name| rendered.contains(&format!("{}::", name)))
+                .cloned()
+        })
+}
+
+/// Bumps `dependency` to the newest version still allowed by its dependents' semver
+/// requirements, mirroring what a maintainer would run by hand after `-Z minimal-versions`
+/// flags it: `cargo update -p <dependency>`.
+async fn bump_dependency(workdir: &Path, rust: &RustVersion, dependency: &str) -> bool {
+    let mut cargo = process::Command::new("cargo")
+        .current_dir(workdir)
+        .arg(format!("+{}", rust.name))
+        .arg("update")
+        .arg("-p")
+        .arg(dependency)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("cargo failed to execute");
+    cargo.wait().await.unwrap().success()
+}
+
+fn locked_version(workdir: &Path, dependency: &str) -> Option<Version> {
+    let lockfile = std::fs::read_to_string(workdir.join("Cargo.lock")).ok()?;
+    lockfile.split("[[package]]").find_map(|block| {
+        if !block.contains(&format!("name = \"{}\"", dependency)) {
+            return None;
+        }
+        block
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("version = \""))
+            .and_then(|rest| rest.strip_suffix('"'))
+            .and_then(|v| v.parse().ok())
+    })
+}
+
+/// For each configured toolchain (other than nightly, which generated the lockfile), runs the
+/// normal feature matrix against the shared minimal-versions lockfile. On a failure whose
+/// offending crate `find_offending_crate` can name (from a resolver error or a compiler
+/// diagnostic), repeatedly bumps that crate upward and retries until either the build passes or no
+/// newer compatible version is left, recording every bump as a `VersionPin` that can be copied
+/// into `rust.requires_pinning` in the config.
+async fn minimal_versions_mode(
+    cfg: &Config,
+    delete_path_sender: &mpsc::Sender<PathBuf>,
+) -> Vec<VersionPin> {
+    let stable_version = get_stable_version().await;
+    let nightly = RustVersion {
+        name: "nightly".to_string(),
+        requires_pinning: Vec::new(),
+    };
+    let scratch = prepare_rust_version(cfg, &nightly, delete_path_sender).await;
+    generate_minimal_lockfile(&scratch.workdir).await;
+    let minimal_lockfile = scratch.workdir.join("Cargo.lock");
+
+    let mut discovered = Vec::new();
+    for rust in cfg.rust.iter().filter(|r| r.name != "nightly") {
+        let env = prepare_rust_version_with_lockfile(
+            cfg,
+            rust,
+            Some(&minimal_lockfile),
+            delete_path_sender,
+        )
+        .await;
+
+        'retry: loop {
+            for feature_set in feature_sets_for_rust(cfg, rust, &stable_version) {
+                let output = exec_cargo_test(&env.workdir, rust, &feature_set, None).await;
+                if output.status.success() {
+                    continue;
+                }
+
+                let offending = match find_offending_crate(&env.workdir, &output) {
+                    Some(name) => name,
+                    None => {
+                        error!(
+                            "minimal-versions build failed on rust={} but no offending crate could be identified",
+                            rust.name
+                        );
+                        break 'retry;
+                    }
+                };
+
+                let version_before = locked_version(&env.workdir, &offending);
+
+                info!(
+                    "minimal-versions: bumping {} to unblock rust={}",
+                    offending, rust.name
+                );
+                if !bump_dependency(&env.workdir, rust, &offending).await {
+                    error!(
+                        "No newer version of {} could unblock rust={}",
+                        offending, rust.name
+                    );
+                    break 'retry;
+                }
+
+                let version_after = locked_version(&env.workdir, &offending);
+                if version_after == version_before {
+                    // `cargo update -p` is a no-op once a crate is already at the newest
+                    // version its dependents' semver requirements allow, but still exits 0 -
+                    // without this check we'd spin on the same failure forever.
+                    error!(
+                        "{} is already at its newest allowed version; it cannot unblock rust={}",
+                        offending, rust.name
+                    );
+                    break 'retry;
+                }
+                if let Some(version) = version_after {
+                    discovered.push(VersionPin {
+                        dependency: offending,
+                        version,
+                    });
+                }
+                continue 'retry;
+            }
+            info!("minimal-versions passed for rust={}", rust.name);
+            break;
+        }
+    }
+
+    discovered
+}
+
+/// The subset of rustc's `--message-format=json` diagnostic shape we care about: a
+/// machine-applicable suggestion is a span with a `suggested_replacement`, the same field
+/// `rustfix`/`cargo fix` key off of.
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcMessage>,
+}
+
+#[derive(Deserialize)]
+struct RustcMessage {
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcMessage>,
+    rendered: Option<String>,
+}
+
+/// rustc's confidence that a suggested replacement compiles to something equivalent; only
+/// `MachineApplicable` is safe to apply unattended the way `cargo fix` does - the others
+/// (e.g. `HasPlaceholders`) can contain literal `/* ... */` placeholder text.
+#[derive(Deserialize, PartialEq)]
+enum Applicability {
+    MachineApplicable,
+    MaybeIncorrect,
+    HasPlaceholders,
+    Unspecified,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<Applicability>,
+}
+
+/// Pulls every machine-applicable suggestion out of a `--message-format=json` build, which
+/// `exec_cargo_test` already requests for every unit.
+fn collect_suggestions(stdout: &[u8]) -> Vec<RustcSpan> {
+    stdout
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| serde_json::from_slice::<CargoMessage>(line).ok())
+        .filter(|msg| msg.reason == "compiler-message")
+        .filter_map(|msg| msg.message)
+        // rustc attaches suggestions to the spans of child `help` diagnostics, so look there
+        // as well as on the top-level message.
+        .flat_map(|message| {
+            let child_spans = message.children.into_iter().flat_map(|child| child.spans);
+            message.spans.into_iter().chain(child_spans)
+        })
+        .filter(|span| {
+            span.suggested_replacement.is_some()
+                && span.suggestion_applicability == Some(Applicability::MachineApplicable)
+        })
+        .collect()
+}
+
+/// Applies suggestions to the files they target, last-byte-offset-first so that applying one
+/// suggestion doesn't invalidate the byte offsets of an earlier one in the same file.
+/// Suggestions that overlap one already applied are skipped, the same conservative rule
+/// `cargo fix` uses to avoid corrupting a file when two lints disagree.
+fn apply_suggestions(workdir: &Path, spans: Vec<RustcSpan>) -> HashMap<PathBuf, (String, String)> {
+    let workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.to_path_buf());
+
+    let mut by_file: HashMap<PathBuf, Vec<RustcSpan>> = HashMap::new();
+    for span in spans {
+        // `file_name` can be absolute (e.g. a dependency under `~/.cargo/registry`) if the
+        // suggestion targets something outside the crate being built; `join` would then
+        // discard `workdir` entirely and point us at a file we don't own. Canonicalize and
+        // require the result to actually live under `workdir` before touching it.
+        let joined = workdir.join(&span.file_name);
+        let resolved = match joined.canonicalize() {
+            Ok(path) => path,
+            Err(_) => continue,
+        };
+        if !resolved.starts_with(&workdir) {
+            continue;
+        }
+
+        by_file.entry(resolved).or_default().push(span);
+    }
+
+    let mut diffs = HashMap::new();
+    for (path, mut spans) in by_file {
+        let original = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+
+        spans.sort_by_key(|span| std::cmp::Reverse(span.byte_start));
+        let mut fixed = original.clone();
+        let mut applied_up_to = fixed.len();
+        for span in spans {
+            if span.byte_end > applied_up_to {
+                continue;
+            }
+            let replacement = span.suggested_replacement.as_deref().unwrap_or_default();
+            fixed.replace_range(span.byte_start..span.byte_end, replacement);
+            applied_up_to = span.byte_start;
+        }
+
+        if fixed != original {
+            std::fs::write(&path, &fixed).unwrap();
+            diffs.insert(path, (original, fixed));
+        }
+    }
+    diffs
+}
+
+/// On a failed `(rust, feature_set)` unit, collects rustc's machine-applicable suggestions
+/// from the already-captured build output, applies them in `env.workdir`, logs a before/after
+/// of every changed file, and re-runs the unit to confirm the fix actually resolves the
+/// failure. `env` must be a private copy (see [`clone_workdir_for_fix`]), not the `PreparedEnv`
+/// shared by the rest of that toolchain's jobs, or applying the fix races those other jobs.
+async fn try_fix_unit(
+    env: &PreparedEnv,
+    rust: &RustVersion,
+    feature_set: &[Feature],
+    failing_output: &std::process::Output,
+    target_dir: Option<&Path>,
+) -> bool {
+    let suggestions = collect_suggestions(&failing_output.stdout);
+    if suggestions.is_empty() {
+        return false;
+    }
+
+    let diffs = apply_suggestions(&env.workdir, suggestions);
+    if diffs.is_empty() {
+        return false;
+    }
+
+    for (path, (before, after)) in &diffs {
+        info!(
+            "Applied rustc suggestion(s) to {}:\n--- before ---\n{}\n--- after ---\n{}",
+            path.display(),
+            before,
+            after
+        );
+    }
+
+    let (output, _) = run_test(&env.workdir, rust, feature_set, target_dir).await;
+    output.status.success()
 }
 
 async fn pin_dependencies(path: &Path, rust: &RustVersion) {
@@ -254,8 +996,79 @@ async fn pin_dependencies(path: &Path, rust: &RustVersion) {
     }
 }
 
+/// Copies any seed corpus checked into the repo at `<corpus_rel_path>/<target>/input` into the
+/// honggfuzz workspace so a run starts warm instead of from scratch every time.
+fn seed_corpus(project_path: &Path, target_workspace: &Path, fuzz_target: &str, cfg: &Fuzzing) {
+    let corpus_rel_path = match cfg.corpus_rel_path.as_ref() {
+        Some(path) => path,
+        None => return,
+    };
+
+    let seed_input = project_path
+        .join(corpus_rel_path)
+        .join(fuzz_target)
+        .join("input");
+    if !seed_input.is_dir() {
+        return;
+    }
+
+    std::fs::create_dir_all(target_workspace).unwrap();
+    let mut copy_options = fs_extra::dir::CopyOptions::new();
+    copy_options.copy_inside = true;
+    copy_options.overwrite = true;
+    fs_extra::dir::copy(&seed_input, target_workspace, &copy_options).unwrap();
+    debug!(
+        "Seeded {} corpus from {}",
+        fuzz_target,
+        seed_input.display()
+    );
+}
+
+/// Copies any crash/hang files left behind in `<target>`'s honggfuzz workspace out to a
+/// durable `<crashes_dir>/<target>/<timestamp>` directory, so each run's crashes are kept
+/// apart from the honggfuzz workspace, and logs the exact command needed to reproduce each one.
+fn persist_crashes(target_workspace: &Path, fuzz_target: &str, crashes_dir: &Path, cfg: &Fuzzing) {
+    let crash_files = match std::fs::read_dir(target_workspace) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.contains("SIGSEGV") || name.contains("SIGABRT") || name.ends_with(".fuzz")
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+
+    if crash_files.is_empty() {
+        return;
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let out_dir = crashes_dir.join(fuzz_target).join(timestamp.to_string());
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    for crash_file in crash_files {
+        let dest = out_dir.join(crash_file.file_name());
+        std::fs::copy(crash_file.path(), &dest).unwrap();
+        error!(
+            "Crash found for {}, saved to {}. Reproduce with: cargo +{} hfuzz run-debug {} {}",
+            fuzz_target,
+            dest.display(),
+            cfg.rust,
+            fuzz_target,
+            dest.display()
+        );
+    }
+}
+
 async fn fuzz_test(base_path: &Path, cfg: &Fuzzing) {
-    let fuzz_targets = std::fs::read_dir(base_path.join(&cfg.rel_path).join("fuzz_targets"))
+    let project_path = base_path.join(&cfg.rel_path);
+    let fuzz_targets = std::fs::read_dir(project_path.join("fuzz_targets"))
         .unwrap()
         .map(|file| {
             file.unwrap()
@@ -269,11 +1082,13 @@ async fn fuzz_test(base_path: &Path, cfg: &Fuzzing) {
         })
         .collect::<Vec<_>>();
 
-    // TODO: add hfuzz inputs from repo
     for fuzz_target in fuzz_targets {
+        let target_workspace = project_path.join("hfuzz_workspace").join(&fuzz_target);
+        seed_corpus(&project_path, &target_workspace, &fuzz_target, cfg);
+
         info!("Fuzzing {}", fuzz_target);
         let cargo = process::Command::new("cargo")
-            .current_dir(base_path.join(&cfg.rel_path))
+            .current_dir(&project_path)
             .env("HFUZZ_BUILD_ARGS", "--features honggfuzz_fuzz")
             .env(
                 "HFUZZ_RUN_ARGS",
@@ -299,6 +1114,10 @@ async fn fuzz_test(base_path: &Path, cfg: &Fuzzing) {
             info!("std err:\n");
             std::io::stdout().write_all(&output.stderr).unwrap();
         }
+
+        if let Some(crashes_dir) = cfg.crashes_out.as_ref() {
+            persist_crashes(&target_workspace, &fuzz_target, crashes_dir, cfg);
+        }
     }
 }
 
@@ -343,25 +1162,112 @@ async fn main() {
         }
     }
 
-    let test_matrix = gen_test_matrix(&cfg).await;
     let (delete_path_sender, delete_path_receiver) = mpsc::channel(4);
     let (trigger, tripwire) = Tripwire::new();
 
     tokio::spawn(delete_paths_on_shutdown(delete_path_receiver, trigger));
 
-    // TODO: allow more parallelism than just amount of rust version to test
-    futures::stream::iter(test_matrix)
+    if opts.find_msrv {
+        let results = find_msrv(&cfg, &delete_path_sender).await;
+        println!("{:<40} min_rust", "feature_set");
+        for result in &results {
+            println!(
+                "{:<40} {}",
+                result.feature_set,
+                result.min_rust.as_deref().unwrap_or("-")
+            );
+        }
+        return;
+    }
+
+    if opts.minimal_versions {
+        let pins = minimal_versions_mode(&cfg, &delete_path_sender).await;
+        println!("{:<30} version", "dependency");
+        for pin in &pins {
+            println!("{:<30} {}", pin.dependency, pin.version);
+        }
+        return;
+    }
+
+    let test_matrix = gen_test_matrix(&cfg).await;
+
+    // Prepare every toolchain's workdir once up front, then flatten the whole matrix into
+    // a single list of (rust, feature_set) jobs. `par` bounds actual `cargo test` jobs
+    // running at once, not how many toolchains are handled concurrently. Every job for a
+    // given toolchain shares the same checked-out workdir, so each gets its own
+    // CARGO_TARGET_DIR - otherwise they'd all serialize on cargo's build-directory lock and
+    // a wide feature powerset on a single toolchain would never actually run in parallel.
+    let mut jobs = Vec::new();
+    for (rust, feature_sets) in test_matrix {
+        let env = prepare_rust_version(&cfg, &rust, &delete_path_sender).await;
+        for (i, feature_set) in feature_sets.into_iter().enumerate() {
+            let target_dir = env.workdir.parent().unwrap().join(format!("target-{}", i));
+            jobs.push((rust.clone(), feature_set, env.clone(), target_dir));
+        }
+    }
+
+    // `cfg.par == 0` means "no limit", matching `for_each_concurrent`'s own convention for
+    // `None`; a literal `Semaphore::new(0)` would instead let `acquire` block forever, so map
+    // it onto the semaphore's own notion of unbounded.
+    let permits = if cfg.par == 0 {
+        Semaphore::MAX_PERMITS
+    } else {
+        cfg.par
+    };
+    let semaphore = Arc::new(Semaphore::new(permits));
+    let results = Arc::new(Mutex::new(Vec::<TestOutcome>::new()));
+    let fix = opts.fix;
+    futures::stream::iter(jobs)
         .take_until_if(tripwire.clone())
-        .for_each_concurrent(cfg.par, |(rust, feature_sets)| {
-            test_rust_version(
-                cfg.clone(),
-                rust.clone(),
-                feature_sets,
-                delete_path_sender.clone(),
-            )
+        .for_each_concurrent(None, |(rust, feature_set, env, target_dir)| {
+            let semaphore = semaphore.clone();
+            let results = results.clone();
+            let delete_path_sender = delete_path_sender.clone();
+            async move {
+                let _permit = semaphore.acquire().await.unwrap();
+                let (output, outcomes) =
+                    run_test(&env.workdir, &rust, &feature_set, Some(&target_dir)).await;
+                if !output.status.success() && fix {
+                    // `env.workdir` is shared with every other feature-set job for this
+                    // toolchain, so mutate a private copy instead of the shared checkout.
+                    let fix_env = clone_workdir_for_fix(&env.workdir, &delete_path_sender).await;
+                    if try_fix_unit(&fix_env, &rust, &feature_set, &output, Some(&target_dir)).await
+                    {
+                        info!(
+                            "Fix applied and verified for rust={}, features=[{}]",
+                            rust.name,
+                            feature_set.iter().map(|f| &f.name).join(",")
+                        );
+                    } else {
+                        info!(
+                            "No applicable fix found for rust={}, features=[{}]",
+                            rust.name,
+                            feature_set.iter().map(|f| &f.name).join(",")
+                        );
+                    }
+                }
+                results.lock().unwrap().extend(outcomes);
+            }
         })
         .await;
 
+    let results = Arc::try_unwrap(results).unwrap().into_inner().unwrap();
+
+    let junit_path = opts
+        .junit_report
+        .or_else(|| cfg.report.as_ref().and_then(|r| r.junit_path.clone()));
+    if let Some(path) = junit_path {
+        write_junit_report(&results, &path);
+    }
+
+    let summary_path = opts
+        .json_report
+        .or_else(|| cfg.report.as_ref().and_then(|r| r.summary_path.clone()));
+    if let Some(path) = summary_path {
+        std::fs::write(&path, serde_json::to_string_pretty(&results).unwrap())
+            .expect("Could not write summary report");
+    }
+
     tokio::time::sleep(Duration::from_millis(1500)).await;
 
     if let Some(ref fuzz) = cfg.fuzzing {